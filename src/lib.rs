@@ -21,10 +21,33 @@
 // SOFTWARE.
 
 pub mod dkubiszewski {
-    use self::utils::ListNode;
     use std::collections::HashMap;
     use std::hash::Hash;
-    use std::rc::Rc;
+
+    /// Assigns a weight to a value stored in the cache.
+    ///
+    /// The cache bounds the total weight of its entries rather than the raw
+    /// entry count, so a handful of heavy values can be made to count for as
+    /// much as many light ones. The default method gives a type a weight of
+    /// `1` for free (`impl Weight for MyType {}`), which makes the weighted
+    /// cache behave exactly like a classic entry-count-bounded one unless a
+    /// caller overrides `weight` with something heavier (e.g. a `Vec<u8>`
+    /// wrapper returning its byte length).
+    pub trait Weight {
+        fn weight(&self) -> usize {
+            1
+        }
+    }
+
+    macro_rules! impl_default_weight {
+        ($($t:ty),* $(,)?) => {
+            $(impl Weight for $t {})*
+        };
+    }
+
+    impl_default_weight!(
+        i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, bool, char, String
+    );
 
     /// LRU cache
     /// # Example
@@ -42,26 +65,34 @@ pub mod dkubiszewski {
     pub struct LruCache<KeyType, ValueType>
     where
         KeyType: Eq + Hash,
-        ValueType: Eq + Hash,
     {
         capacity: usize,
-        map: HashMap<KeyType, (ValueType, Rc<utils::ListNode<KeyType>>)>,
-        queue: utils::List<KeyType>,
+        current_weight: usize,
+        map: HashMap<KeyType, utils::NodePtr<KeyType, ValueType>>,
+        queue: utils::List<KeyType, ValueType>,
     }
 
     impl<KeyType, ValueType> LruCache<KeyType, ValueType>
     where
-        KeyType: Eq + Hash + Copy,
-        ValueType: Eq + Hash,
+        KeyType: Eq + Hash + Clone,
+        ValueType: Weight,
     {
         /// Creates LRU cache with specific capacity.
         ///
+        /// The capacity bounds the total weight of the entries held by the
+        /// cache, not the number of entries. `Weight::weight` defaults to
+        /// `1`, so this is equivalent to an entry-count capacity unless
+        /// `ValueType` overrides `weight` to count for more. An entry whose
+        /// own weight exceeds the capacity is still stored by `put`, alone,
+        /// once every other entry has been evicted.
+        ///
         /// # Arguments
         ///
         /// * `capacity` Capacity of the cache.
         pub fn new(capacity: usize) -> Self {
             LruCache {
-                capacity: capacity,
+                capacity,
+                current_weight: 0,
                 map: HashMap::new(),
                 queue: utils::List::new(),
             }
@@ -74,26 +105,37 @@ pub mod dkubiszewski {
         /// * `key` The key.
         /// * `value` The value.
         pub fn put(&mut self, key: KeyType, value: ValueType) {
-            if let Some((_map_value, node)) = self.map.get_mut(&key) {
-                self.queue.remove_node(node.clone());
-                self.map.remove(&key);
-            } else if self.map.len() == self.capacity {
-                self.map.remove(
-                    if let ListNode::Link {
-                        value,
-                        prev: _,
-                        next: _,
-                    } = self.queue.back().as_ref()
-                    {
-                        value
-                    } else {
-                        panic!("Logic error");
-                    },
-                );
-                self.queue.pop_back();
+            if let Some(node) = self.map.remove(&key) {
+                self.current_weight -= self.queue.value(node).weight();
+                self.queue.remove_node(node);
+                self.queue.drop_node(node);
+            }
+            while !self.map.is_empty() && self.current_weight + value.weight() > self.capacity {
+                let evicted = self.queue.pop_back().expect("Logic error");
+                self.current_weight -= evicted.1.weight();
+                self.map.remove(&evicted.0);
+            }
+            self.current_weight += value.weight();
+            let front_node = self.queue.push_front(key.clone(), value);
+            self.map.insert(key, front_node);
+        }
+
+        /// Changes the capacity of the cache.
+        ///
+        /// If the new capacity is smaller than the current total weight,
+        /// the least recently used entries are evicted until the cache fits
+        /// within the new capacity.
+        ///
+        /// # Arguments
+        ///
+        /// * `capacity` New capacity of the cache.
+        pub fn change_capacity(&mut self, capacity: usize) {
+            self.capacity = capacity;
+            while !self.map.is_empty() && self.current_weight > self.capacity {
+                let evicted = self.queue.pop_back().expect("Logic error");
+                self.current_weight -= evicted.1.weight();
+                self.map.remove(&evicted.0);
             }
-            let front_node = self.queue.push_front(key);
-            self.map.insert(key, (value, front_node));
         }
 
         /// Get data from the cache.
@@ -103,312 +145,457 @@ pub mod dkubiszewski {
         /// * `key` The key.
         pub fn get(&mut self, key: &KeyType) -> Option<&ValueType> {
             match self.map.get(key) {
-                Some((value, node)) => {
-                    self.queue.remove_node(node.clone());
-                    self.queue.push_node_front(node.clone());
-                    Some(&value)
+                Some(&node) => {
+                    self.queue.remove_node(node);
+                    self.queue.push_node_front(node);
+                    Some(self.queue.value(node))
                 }
                 None => None,
             }
         }
+
+        /// Iterates over the cache in recency order, most-recently-used
+        /// first, without affecting that order.
+        pub fn iter(&self) -> Iter<'_, KeyType, ValueType> {
+            self.queue.iter()
+        }
+
+        /// Iterates over the cache in recency order, most-recently-used
+        /// first, without affecting that order.
+        pub fn iter_mut(&mut self) -> IterMut<'_, KeyType, ValueType> {
+            self.queue.iter_mut()
+        }
+
+        /// Looks up a value without promoting it in the recency queue.
+        ///
+        /// # Arguments
+        ///
+        /// * `key` The key.
+        pub fn peek(&self, key: &KeyType) -> Option<&ValueType> {
+            self.map.get(key).map(|&node| self.queue.value(node))
+        }
+
+        /// Returns whether `key` is present in the cache, without promoting
+        /// it in the recency queue.
+        ///
+        /// # Arguments
+        ///
+        /// * `key` The key.
+        pub fn contains_key(&self, key: &KeyType) -> bool {
+            self.map.contains_key(key)
+        }
+
+        /// Removes and returns the value for `key`, if present.
+        ///
+        /// # Arguments
+        ///
+        /// * `key` The key.
+        pub fn remove(&mut self, key: &KeyType) -> Option<ValueType> {
+            let node = self.map.remove(key)?;
+            let (_, value) = self.queue.take_node(node);
+            self.current_weight -= value.weight();
+            Some(value)
+        }
+
+        /// Returns the number of entries currently in the cache.
+        pub fn len(&self) -> usize {
+            self.map.len()
+        }
+
+        /// Returns `true` if the cache holds no entries.
+        pub fn is_empty(&self) -> bool {
+            self.map.is_empty()
+        }
+
+        /// Returns the cache's current capacity.
+        pub fn capacity(&self) -> usize {
+            self.capacity
+        }
     }
-    mod utils {
-        use std::{cell::RefCell, rc::Rc};
 
-        #[derive(Debug, PartialEq)]
-        pub enum ListNode<T> {
-            None,
-            Link {
-                value: T,
-                prev: RefCell<Rc<ListNode<T>>>,
-                next: RefCell<Rc<ListNode<T>>>,
-            },
+    impl<KeyType, ValueType> IntoIterator for LruCache<KeyType, ValueType>
+    where
+        KeyType: Eq + Hash,
+    {
+        type Item = (KeyType, ValueType);
+        type IntoIter = IntoIter<KeyType, ValueType>;
+
+        /// Consumes the cache in recency order, most-recently-used first.
+        fn into_iter(self) -> IntoIter<KeyType, ValueType> {
+            self.queue.into_iter()
+        }
+    }
+
+    pub use self::utils::{IntoIter, Iter, IterMut};
+
+    /// `serde` support, gated behind the `serde` cargo feature.
+    ///
+    /// Mirrors the approach the `serde.rs` module takes for
+    /// `linked-hash-map`: the cache is serialized as an ordered sequence of
+    /// `(key, value)` pairs, front-to-back, so that recency order survives
+    /// the round trip. Since `LruCache` additionally bounds a capacity,
+    /// that's serialized alongside the entries and used to rebuild a cache
+    /// of the same size on the way back in.
+    #[cfg(feature = "serde")]
+    mod serde_impl {
+        use super::{LruCache, Weight};
+        use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+        use serde::ser::{Serialize, SerializeTuple, Serializer};
+        use std::fmt;
+        use std::hash::Hash;
+        use std::marker::PhantomData;
+
+        impl<KeyType, ValueType> Serialize for LruCache<KeyType, ValueType>
+        where
+            KeyType: Eq + Hash + Clone + Serialize,
+            ValueType: Weight + Serialize,
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&self.capacity)?;
+                tuple.serialize_element(&self.iter().collect::<Vec<_>>())?;
+                tuple.end()
+            }
+        }
+
+        struct LruCacheVisitor<KeyType, ValueType> {
+            _marker: PhantomData<(KeyType, ValueType)>,
         }
 
-        pub struct List<T> {
-            head: RefCell<Rc<ListNode<T>>>,
-            tail: RefCell<Rc<ListNode<T>>>,
+        impl<'de, KeyType, ValueType> Visitor<'de> for LruCacheVisitor<KeyType, ValueType>
+        where
+            KeyType: Eq + Hash + Clone + Deserialize<'de>,
+            ValueType: Weight + Deserialize<'de>,
+        {
+            type Value = LruCache<KeyType, ValueType>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a (capacity, entries) tuple produced by LruCache::serialize")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let capacity = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                let entries: Vec<(KeyType, ValueType)> = seq
+                    .next_element()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
+
+                let mut cache = LruCache::new(capacity);
+                // `entries` is stored most-recently-used first; re-`put`ting
+                // it back-to-front restores the same recency order.
+                for (key, value) in entries.into_iter().rev() {
+                    cache.put(key, value);
+                }
+                Ok(cache)
+            }
         }
 
-        impl<T> List<T>
+        impl<'de, KeyType, ValueType> Deserialize<'de> for LruCache<KeyType, ValueType>
         where
-            T: Copy,
+            KeyType: Eq + Hash + Clone + Deserialize<'de>,
+            ValueType: Weight + Deserialize<'de>,
         {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_tuple(
+                    2,
+                    LruCacheVisitor {
+                        _marker: PhantomData,
+                    },
+                )
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use crate::dkubiszewski::LruCache;
+
+            #[test]
+            fn round_trip_preserves_capacity_and_recency_order() {
+                let mut lru: LruCache<i32, String> = LruCache::new(2);
+
+                lru.put(1, String::from("a"));
+                lru.put(2, String::from("b"));
+                lru.get(&1);
+                lru.put(3, String::from("c"));
+
+                let encoded = serde_json::to_string(&lru).unwrap();
+                let mut decoded: LruCache<i32, String> =
+                    serde_json::from_str(&encoded).unwrap();
+
+                assert_eq!(None, decoded.get(&2));
+                assert_eq!("a", decoded.get(&1).unwrap());
+                assert_eq!("c", decoded.get(&3).unwrap());
+
+                decoded.put(4, String::from("d"));
+                assert_eq!(None, decoded.get(&1));
+            }
+        }
+    }
+
+    mod utils {
+        use std::marker::PhantomData;
+        use std::ptr::NonNull;
+
+        pub type NodePtr<K, V> = NonNull<Node<K, V>>;
+
+        pub struct Node<K, V> {
+            key: K,
+            value: V,
+            prev: Option<NodePtr<K, V>>,
+            next: Option<NodePtr<K, V>>,
+        }
+
+        /// An intrusive doubly-linked list backed by a raw-pointer arena.
+        ///
+        /// Nodes are boxed individually, leaked with `Box::leak` and linked by
+        /// `NonNull` pointers rather than `Rc<RefCell<..>>`, so moving a node
+        /// to the front (the hot path on every `get`/`put`) is a handful of
+        /// pointer writes instead of several refcount bumps and heap
+        /// allocations. The list owns every node it hands out and is
+        /// responsible for freeing them, either via `pop_back`/`pop_front`/
+        /// `take_node`/`drop_node` or when the list itself is dropped.
+        pub struct List<K, V> {
+            head: Option<NodePtr<K, V>>,
+            tail: Option<NodePtr<K, V>>,
+        }
+
+        impl<K, V> List<K, V> {
             pub fn new() -> Self {
                 Self {
-                    head: RefCell::new(Rc::new(ListNode::None)),
-                    tail: RefCell::new(Rc::new(ListNode::None)),
+                    head: None,
+                    tail: None,
                 }
             }
 
-            pub fn push_front(&mut self, value: T) -> Rc<ListNode<T>> {
-                let new_node = Rc::new(ListNode::Link {
-                    value: value,
-                    prev: RefCell::new(Rc::new(ListNode::None)),
-                    next: RefCell::new(self.head.borrow().clone()),
+            /// Returns the value stored in `node`.
+            ///
+            /// # Safety
+            ///
+            /// `node` must have been handed out by this list and not yet
+            /// dropped via `drop_node`, `pop_back`, `pop_front` or
+            /// `take_node`.
+            pub fn value(&self, node: NodePtr<K, V>) -> &V {
+                unsafe { &node.as_ref().value }
+            }
+
+            pub fn push_front(&mut self, key: K, value: V) -> NodePtr<K, V> {
+                let node = Box::new(Node {
+                    key,
+                    value,
+                    prev: None,
+                    next: None,
                 });
+                let node = NonNull::from(Box::leak(node));
+                self.push_node_front(node);
+                node
+            }
 
-                if let ListNode::Link {
-                    value: _,
-                    prev,
-                    next: _,
-                } = self.head.get_mut().as_ref()
-                {
-                    prev.replace(new_node.clone());
+            pub fn push_node_front(&mut self, mut node: NodePtr<K, V>) {
+                unsafe {
+                    node.as_mut().prev = None;
+                    node.as_mut().next = self.head;
                 }
-                self.head.replace(new_node.clone());
 
-                if let ListNode::None = self.tail.get_mut().as_ref() {
-                    self.tail.replace(new_node.clone());
+                if let Some(mut head) = self.head {
+                    unsafe {
+                        head.as_mut().prev = Some(node);
+                    }
                 }
+                self.head = Some(node);
 
-                new_node
+                if self.tail.is_none() {
+                    self.tail = Some(node);
+                }
             }
 
-            pub fn push_node_front(&mut self, node: Rc<ListNode<T>>) {
-                if let ListNode::Link {
-                    value: _,
-                    prev,
-                    next,
-                } = node.as_ref()
-                {
-                    prev.replace(Rc::new(ListNode::None));
-                    next.replace(self.head.borrow().clone());
-                }
+            pub fn remove_node(&mut self, node: NodePtr<K, V>) {
+                let (prev, next) = unsafe { (node.as_ref().prev, node.as_ref().next) };
 
-                if let ListNode::Link {
-                    value: _,
-                    prev,
-                    next: _,
-                } = self.head.get_mut().as_ref()
-                {
-                    prev.replace(node.clone());
+                match prev {
+                    Some(mut prev) => unsafe { prev.as_mut().next = next },
+                    None => self.head = next,
                 }
-                self.head.replace(node.clone());
 
-                if let ListNode::None = self.tail.get_mut().as_ref() {
-                    self.tail.replace(node.clone());
+                match next {
+                    Some(mut next) => unsafe { next.as_mut().prev = prev },
+                    None => self.tail = prev,
                 }
             }
 
-            pub fn remove_node(&mut self, node: Rc<ListNode<T>>) {
-                if let ListNode::Link {
-                    value: _,
-                    prev,
-                    next,
-                } = node.as_ref()
-                {
-                    let new_next = next;
-                    if let ListNode::Link {
-                        value: _,
-                        prev: _,
-                        next,
-                    } = prev.borrow_mut().as_ref()
-                    {
-                        next.replace(new_next.borrow().clone());
-                    } else {
-                        self.head.replace(new_next.borrow().clone());
-                    }
-
-                    let new_prev = prev;
-                    if let ListNode::Link {
-                        value: _,
-                        prev,
-                        next: _,
-                    } = next.borrow_mut().as_ref()
-                    {
-                        prev.replace(new_prev.borrow().clone());
-                    } else {
-                        self.tail.replace(new_prev.borrow().clone());
-                    }
+            /// Frees `node`, which must have been handed out by this list,
+            /// already unlinked with `remove_node`, and not yet dropped.
+            pub fn drop_node(&mut self, node: NodePtr<K, V>) {
+                unsafe {
+                    drop(Box::from_raw(node.as_ptr()));
                 }
             }
 
-            pub fn back(&self) -> Rc<ListNode<T>> {
-                self.tail.borrow().clone()
+            /// Unlinks `node` and hands its key/value back to the caller.
+            ///
+            /// # Safety
+            ///
+            /// `node` must have been handed out by this list and not yet
+            /// dropped via `drop_node`, `pop_back`, `pop_front` or
+            /// `take_node`.
+            pub fn take_node(&mut self, node: NodePtr<K, V>) -> (K, V) {
+                self.remove_node(node);
+                let node = unsafe { Box::from_raw(node.as_ptr()) };
+                (node.key, node.value)
             }
 
-            pub fn pop_back(&mut self) {
-                let new_tail: RefCell<Rc<ListNode<T>>> = RefCell::new(Rc::new(ListNode::None));
+            pub fn pop_back(&mut self) -> Option<(K, V)> {
+                let tail = self.tail?;
+                Some(self.take_node(tail))
+            }
 
-                if let ListNode::Link {
-                    value: _,
-                    prev,
-                    next: _,
-                } = self.tail.get_mut().as_ref()
-                {
-                    new_tail.replace(prev.borrow().clone());
+            pub fn pop_front(&mut self) -> Option<(K, V)> {
+                let head = self.head?;
+                Some(self.take_node(head))
+            }
 
-                    if let ListNode::Link {
-                        value: _,
-                        prev: _,
-                        next,
-                    } = prev.borrow_mut().as_ref()
-                    {
-                        next.replace(Rc::new(ListNode::None));
-                    }
+            /// Iterates front-to-back, i.e. most-recently-used first.
+            pub fn iter(&self) -> Iter<'_, K, V> {
+                Iter {
+                    next: self.head,
+                    _marker: PhantomData,
                 }
+            }
 
-                if let ListNode::None = new_tail.borrow().as_ref() {
-                    self.head.replace(new_tail.borrow().clone());
+            /// Iterates front-to-back, i.e. most-recently-used first.
+            pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+                IterMut {
+                    next: self.head,
+                    _marker: PhantomData,
                 }
+            }
+        }
+
+        impl<K, V> IntoIterator for List<K, V> {
+            type Item = (K, V);
+            type IntoIter = IntoIter<K, V>;
+
+            /// Consumes the list front-to-back, i.e. most-recently-used first.
+            fn into_iter(self) -> IntoIter<K, V> {
+                IntoIter { list: self }
+            }
+        }
+
+        pub struct Iter<'a, K, V> {
+            next: Option<NodePtr<K, V>>,
+            _marker: PhantomData<&'a Node<K, V>>,
+        }
+
+        impl<'a, K, V> Iterator for Iter<'a, K, V> {
+            type Item = (&'a K, &'a V);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let node = unsafe { self.next?.as_ref() };
+                self.next = node.next;
+                Some((&node.key, &node.value))
+            }
+        }
+
+        pub struct IterMut<'a, K, V> {
+            next: Option<NodePtr<K, V>>,
+            _marker: PhantomData<&'a mut Node<K, V>>,
+        }
+
+        impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+            type Item = (&'a K, &'a mut V);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let mut node = self.next?;
+                let node = unsafe { node.as_mut() };
+                self.next = node.next;
+                Some((&node.key, &mut node.value))
+            }
+        }
+
+        pub struct IntoIter<K, V> {
+            list: List<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoIter<K, V> {
+            type Item = (K, V);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.list.pop_front()
+            }
+        }
 
-                self.tail.replace(new_tail.borrow().clone());
+        impl<K, V> Drop for List<K, V> {
+            fn drop(&mut self) {
+                let mut current = self.head;
+                while let Some(node) = current {
+                    unsafe {
+                        current = node.as_ref().next;
+                        drop(Box::from_raw(node.as_ptr()));
+                    }
+                }
             }
         }
 
         #[cfg(test)]
         mod tests {
-            use crate::dkubiszewski::utils::{List, ListNode};
+            use super::List;
 
             #[test]
             fn empty_list() {
-                let ll: List<i32> = List::new();
-                assert_eq!(&ListNode::None, ll.back().as_ref());
+                let mut ll: List<i32, i32> = List::new();
+                assert_eq!(None, ll.pop_back());
             }
 
             #[test]
             fn add_remove_elements() {
-                let mut ll: List<i32> = List::new();
-
-                ll.push_front(1);
-                assert_eq!(
-                    &1,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-                ll.push_front(2);
-                assert_eq!(
-                    &1,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-
-                ll.pop_back();
-                assert_eq!(
-                    &2,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
+                let mut ll: List<i32, i32> = List::new();
+
+                ll.push_front(1, 10);
+                ll.push_front(2, 20);
 
-                ll.pop_back();
-                assert_eq!(&ListNode::None, ll.back().as_ref());
+                assert_eq!((1, 10), ll.pop_back().unwrap());
+                assert_eq!((2, 20), ll.pop_back().unwrap());
+                assert_eq!(None, ll.pop_back());
             }
 
             #[test]
             fn add_remove_elements_and_add() {
-                let mut ll: List<i32> = List::new();
-
-                ll.push_front(1);
-                assert_eq!(
-                    &1,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-                ll.push_front(2);
-                assert_eq!(
-                    &1,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-
-                ll.pop_back();
-                assert_eq!(
-                    &2,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-
-                ll.pop_back();
-                assert_eq!(&ListNode::None, ll.back().as_ref());
-
-                ll.push_front(15);
-                assert_eq!(
-                    &15,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
+                let mut ll: List<i32, i32> = List::new();
+
+                ll.push_front(1, 10);
+                ll.push_front(2, 20);
+
+                assert_eq!((1, 10), ll.pop_back().unwrap());
+                assert_eq!((2, 20), ll.pop_back().unwrap());
+                assert_eq!(None, ll.pop_back());
+
+                ll.push_front(15, 150);
+                assert_eq!((15, 150), ll.pop_back().unwrap());
             }
 
             #[test]
             fn remove_middle_node() {
-                let mut ll: List<i32> = List::new();
+                let mut ll: List<i32, i32> = List::new();
 
-                ll.push_front(1);
-                let middle_node = ll.push_front(2);
-                ll.push_front(3);
+                ll.push_front(1, 10);
+                let middle_node = ll.push_front(2, 20);
+                ll.push_front(3, 30);
 
                 ll.remove_node(middle_node);
+                ll.drop_node(middle_node);
 
-                assert_eq!(
-                    &1,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-
-                ll.pop_back();
-                assert_eq!(
-                    &3,
-                    match ll.back().as_ref() {
-                        ListNode::None => panic!("Value should be set"),
-                        ListNode::Link {
-                            value,
-                            prev: _,
-                            next: _,
-                        } => value,
-                    }
-                );
-
-                ll.pop_back();
-                assert_eq!(&ListNode::None, ll.back().as_ref());
+                assert_eq!((1, 10), ll.pop_back().unwrap());
+                assert_eq!((3, 30), ll.pop_back().unwrap());
+                assert_eq!(None, ll.pop_back());
             }
         }
     }
@@ -474,5 +661,243 @@ pub mod dkubiszewski {
             assert_eq!(11, *lru.get(&1).unwrap());
             assert_eq!(6, *lru.get(&2).unwrap());
         }
+
+        #[test]
+        fn change_capacity_evicts_oldest_when_shrinking() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(3);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+            lru.put(3, 7);
+
+            lru.change_capacity(1);
+
+            assert_eq!(None, lru.get(&1));
+            assert_eq!(None, lru.get(&2));
+            assert_eq!(7, *lru.get(&3).unwrap());
+        }
+
+        #[test]
+        fn default_weight_is_one() {
+            use super::Weight;
+
+            assert_eq!(1, 5i32.weight());
+            assert_eq!(1, String::from("anything").weight());
+        }
+
+        #[test]
+        fn unweighted_cache_still_bounds_by_entry_count() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+            lru.put(3, 7);
+
+            assert_eq!(None, lru.get(&1));
+            assert_eq!(6, *lru.get(&2).unwrap());
+            assert_eq!(7, *lru.get(&3).unwrap());
+        }
+
+        #[test]
+        fn custom_weight_evicts_to_stay_under_capacity() {
+            struct Blob(usize);
+
+            impl super::Weight for Blob {
+                fn weight(&self) -> usize {
+                    self.0
+                }
+            }
+
+            let mut lru: LruCache<i32, Blob> = LruCache::new(10);
+
+            lru.put(1, Blob(4));
+            lru.put(2, Blob(4));
+            lru.put(3, Blob(4));
+
+            assert!(lru.get(&1).is_none());
+            assert_eq!(4, lru.get(&2).unwrap().0);
+            assert_eq!(4, lru.get(&3).unwrap().0);
+        }
+
+        #[test]
+        fn change_capacity_grows_without_evicting() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+
+            lru.change_capacity(3);
+            lru.put(3, 7);
+
+            assert_eq!(5, *lru.get(&1).unwrap());
+            assert_eq!(6, *lru.get(&2).unwrap());
+            assert_eq!(7, *lru.get(&3).unwrap());
+        }
+
+        #[test]
+        fn dropping_the_cache_frees_all_nodes() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(3);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+            lru.put(3, 7);
+
+            drop(lru);
+        }
+
+        #[test]
+        fn string_keys_are_cloned_not_copied() {
+            let mut lru: LruCache<String, i32> = LruCache::new(2);
+
+            lru.put(String::from("a"), 1);
+            lru.put(String::from("b"), 2);
+            lru.put(String::from("c"), 3);
+
+            assert_eq!(None, lru.get(&String::from("a")));
+            assert_eq!(2, *lru.get(&String::from("b")).unwrap());
+            assert_eq!(3, *lru.get(&String::from("c")).unwrap());
+        }
+
+        #[test]
+        fn values_need_not_be_eq_or_hash() {
+            // Values are never compared or hashed by the cache, so a type
+            // like this connection handle that implements neither no longer
+            // needs to fake the bounds just to be cacheable.
+            struct ConnectionHandle {
+                id: u32,
+            }
+
+            impl super::Weight for ConnectionHandle {}
+
+            let mut lru: LruCache<i32, ConnectionHandle> = LruCache::new(1);
+
+            lru.put(1, ConnectionHandle { id: 42 });
+
+            assert_eq!(42, lru.get(&1).unwrap().id);
+        }
+
+        #[test]
+        fn iter_walks_most_recently_used_first() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(3);
+
+            lru.put(1, 10);
+            lru.put(2, 20);
+            lru.put(3, 30);
+
+            let entries: Vec<(&i32, &i32)> = lru.iter().collect();
+            assert_eq!(vec![(&3, &30), (&2, &20), (&1, &10)], entries);
+        }
+
+        #[test]
+        fn iter_does_not_affect_recency() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 10);
+            lru.put(2, 20);
+
+            let _ = lru.iter().collect::<Vec<_>>();
+            lru.put(3, 30);
+
+            assert_eq!(None, lru.get(&1));
+            assert_eq!(20, *lru.get(&2).unwrap());
+        }
+
+        #[test]
+        fn iter_mut_allows_updating_values_in_place() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 10);
+            lru.put(2, 20);
+
+            for (_, value) in lru.iter_mut() {
+                *value += 1;
+            }
+
+            assert_eq!(11, *lru.get(&1).unwrap());
+            assert_eq!(21, *lru.get(&2).unwrap());
+        }
+
+        #[test]
+        fn into_iter_consumes_in_recency_order() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(3);
+
+            lru.put(1, 10);
+            lru.put(2, 20);
+            lru.put(3, 30);
+
+            let entries: Vec<(i32, i32)> = lru.into_iter().collect();
+            assert_eq!(vec![(3, 30), (2, 20), (1, 10)], entries);
+        }
+
+        #[test]
+        fn peek_does_not_affect_recency() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+
+            assert_eq!(5, *lru.peek(&1).unwrap());
+            assert_eq!(None, lru.peek(&3));
+
+            lru.put(3, 7);
+
+            assert_eq!(None, lru.get(&1));
+            assert_eq!(6, *lru.get(&2).unwrap());
+        }
+
+        #[test]
+        fn contains_key_reports_presence_without_promoting() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+
+            assert!(lru.contains_key(&1));
+            assert!(!lru.contains_key(&3));
+
+            lru.put(3, 7);
+
+            assert!(!lru.contains_key(&1));
+            assert!(lru.contains_key(&2));
+            assert!(lru.contains_key(&3));
+        }
+
+        #[test]
+        fn remove_unlinks_entry_and_returns_its_value() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(3);
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+
+            assert_eq!(Some(5), lru.remove(&1));
+            assert_eq!(None, lru.remove(&1));
+            assert_eq!(None, lru.get(&1));
+
+            lru.put(3, 7);
+            lru.put(4, 8);
+
+            assert_eq!(6, *lru.get(&2).unwrap());
+            assert_eq!(7, *lru.get(&3).unwrap());
+            assert_eq!(8, *lru.get(&4).unwrap());
+        }
+
+        #[test]
+        fn len_is_empty_and_capacity_accessors() {
+            let mut lru: LruCache<i32, i32> = LruCache::new(2);
+
+            assert_eq!(0, lru.len());
+            assert!(lru.is_empty());
+            assert_eq!(2, lru.capacity());
+
+            lru.put(1, 5);
+            lru.put(2, 6);
+
+            assert_eq!(2, lru.len());
+            assert!(!lru.is_empty());
+
+            lru.remove(&1);
+
+            assert_eq!(1, lru.len());
+        }
     }
 }